@@ -0,0 +1,247 @@
+//! JSON REST API mounted at `/api/v1`, alongside the HTML/form site.
+//!
+//! Authentication is a signed JWT (HS256) handed out by `/api/v1/login` and
+//! passed back as `Authorization: Bearer <token>`. The [`CurrentUser`]
+//! extractor decodes and validates that token against the same
+//! `AppState::key` material used to sign the session cookies, then loads
+//! the `User` it names so handlers can use it directly.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, State},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use axum_extra::extract::cookie::Key;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{AppDb, Post, User, UserRole};
+use crate::{ids, AppState};
+
+pub fn router<D: AppDb + Send + Sync + 'static>() -> Router<AppState<D>> {
+    Router::new()
+        .route("/login", post(login::<D>))
+        .route("/posts", get(list_posts::<D>).post(create_post::<D>))
+        .route(
+            "/users/:id",
+            get(get_user::<D>).delete(delete_user::<D>),
+        )
+}
+
+/// JSON error body + status code for every failure mode the API can hit.
+pub enum ApiError {
+    MissingCredentials,
+    InvalidCredentials,
+    MissingToken,
+    InvalidToken,
+    MissingUser,
+    AccountDisabled,
+    Forbidden,
+    Internal,
+}
+
+impl ApiError {
+    fn status_and_message(&self) -> (StatusCode, &'static str) {
+        match self {
+            ApiError::MissingCredentials => (StatusCode::BAD_REQUEST, "missing credentials"),
+            ApiError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "invalid credentials"),
+            ApiError::MissingToken => (StatusCode::UNAUTHORIZED, "missing bearer token"),
+            ApiError::InvalidToken => (StatusCode::UNAUTHORIZED, "invalid or expired token"),
+            ApiError::MissingUser => (StatusCode::NOT_FOUND, "user not found"),
+            ApiError::AccountDisabled => (StatusCode::FORBIDDEN, "account disabled"),
+            ApiError::Forbidden => (StatusCode::FORBIDDEN, "insufficient permissions"),
+            ApiError::Internal => (StatusCode::INTERNAL_SERVER_ERROR, "internal error"),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = self.status_and_message();
+        (
+            status,
+            Json(serde_json::json!({ "status": status.as_u16(), "message": message })),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    role: UserRole,
+    exp: usize,
+}
+
+const TOKEN_TTL_SECS: u64 = 60 * 60 * 24;
+
+fn jwt_key_bytes(key: &Key) -> &[u8] {
+    key.master()
+}
+
+#[derive(Deserialize)]
+struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Serialize)]
+struct LoginResponse {
+    token: String,
+}
+
+async fn login<D: AppDb>(
+    State(state): State<AppState<D>>,
+    Json(req): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    if req.username.is_empty() || req.password.is_empty() {
+        return Err(ApiError::MissingCredentials);
+    }
+
+    let mut db = state.db.lock().map_err(|_| ApiError::Internal)?;
+    let mut user = db
+        .get_user_by_name(&req.username)
+        .map_err(|_| ApiError::InvalidCredentials)?;
+
+    if !user.check_password(&req.password) {
+        return Err(ApiError::InvalidCredentials);
+    }
+    if user.disabled() {
+        return Err(ApiError::AccountDisabled);
+    }
+    db.update_user(user.clone()).ok();
+    drop(db);
+
+    let exp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| ApiError::Internal)?
+        .as_secs()
+        + TOKEN_TTL_SECS;
+
+    let claims = Claims {
+        sub: user.name().to_owned(),
+        role: user.role(),
+        exp: exp as usize,
+    };
+
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt_key_bytes(&state.key)),
+    )
+    .map_err(|_| ApiError::Internal)?;
+
+    Ok(Json(LoginResponse { token }))
+}
+
+/// Extracts and validates the caller's `Authorization: Bearer` JWT, then
+/// loads the `User` it names.
+pub struct CurrentUser(pub User);
+
+#[async_trait]
+impl<D: AppDb + Send + Sync> FromRequestParts<AppState<D>> for CurrentUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<D>,
+    ) -> Result<Self, Self::Rejection> {
+        let header = parts
+            .headers
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or(ApiError::MissingToken)?;
+        let token = header.strip_prefix("Bearer ").ok_or(ApiError::MissingToken)?;
+
+        let data = decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(jwt_key_bytes(&state.key)),
+            &Validation::default(),
+        )
+        .map_err(|_| ApiError::InvalidToken)?;
+
+        let db = state.db.lock().map_err(|_| ApiError::Internal)?;
+        let user = db
+            .get_user_by_name(&data.claims.sub)
+            .map_err(|_| ApiError::MissingUser)?;
+
+        if user.disabled() {
+            return Err(ApiError::AccountDisabled);
+        }
+
+        Ok(CurrentUser(user))
+    }
+}
+
+/// Rejects the request unless [`CurrentUser`] resolves to an admin.
+pub struct AdminUser(pub User);
+
+#[async_trait]
+impl<D: AppDb + Send + Sync> FromRequestParts<AppState<D>> for AdminUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<D>,
+    ) -> Result<Self, Self::Rejection> {
+        let CurrentUser(user) = CurrentUser::from_request_parts(parts, state).await?;
+        if user.role() == UserRole::Admin {
+            Ok(AdminUser(user))
+        } else {
+            Err(ApiError::Forbidden)
+        }
+    }
+}
+
+async fn list_posts<D: AppDb>(
+    State(state): State<AppState<D>>,
+) -> Result<Json<Vec<Post>>, ApiError> {
+    let db = state.db.lock().map_err(|_| ApiError::Internal)?;
+    db.get_posts().map(Json).map_err(|_| ApiError::Internal)
+}
+
+#[derive(Deserialize)]
+struct CreatePostRequest {
+    contents: String,
+}
+
+async fn create_post<D: AppDb>(
+    State(state): State<AppState<D>>,
+    CurrentUser(user): CurrentUser,
+    Json(req): Json<CreatePostRequest>,
+) -> Result<Json<Post>, ApiError> {
+    let mut db = state.db.lock().map_err(|_| ApiError::Internal)?;
+    let post = Post::new(&user, &req.contents);
+    let post = db.add_post(post).map_err(|_| ApiError::Internal)?;
+    Ok(Json(post))
+}
+
+async fn get_user<D: AppDb>(
+    State(state): State<AppState<D>>,
+    Path(encoded_id): Path<String>,
+) -> Result<Json<User>, ApiError> {
+    let user_id = ids::decode(&encoded_id).ok_or(ApiError::MissingUser)?;
+
+    let db = state.db.lock().map_err(|_| ApiError::Internal)?;
+    db.get_user_by_id(user_id)
+        .map(Json)
+        .map_err(|_| ApiError::MissingUser)
+}
+
+/// Admin-only: permanently removes a user account.
+async fn delete_user<D: AppDb>(
+    State(state): State<AppState<D>>,
+    AdminUser(_admin): AdminUser,
+    Path(encoded_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = ids::decode(&encoded_id).ok_or(ApiError::MissingUser)?;
+
+    let mut db = state.db.lock().map_err(|_| ApiError::Internal)?;
+    db.delete_user(user_id).map_err(|_| ApiError::MissingUser)?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,38 @@
+//! Profile picture processing for `POST /settings/avatar`.
+//!
+//! Uploads are decoded, center-cropped to a square, and re-encoded as a
+//! normalized 256x256 PNG. Re-encoding (rather than storing the upload
+//! as-is) strips EXIF/metadata and caps how large a single avatar can be.
+
+use anyhow::{anyhow, Result};
+use image::imageops::FilterType;
+use image::GenericImageView;
+
+pub const AVATAR_SIZE: u32 = 256;
+const ALLOWED_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/webp"];
+
+pub fn is_allowed_mime_type(content_type: &str) -> bool {
+    ALLOWED_MIME_TYPES.contains(&content_type)
+}
+
+/// Decodes `bytes`, crops to a centered square, and resizes to
+/// [`AVATAR_SIZE`], returning PNG-encoded bytes.
+pub fn process_to_thumbnail(bytes: &[u8]) -> Result<Vec<u8>> {
+    let img = image::load_from_memory(bytes).map_err(|e| anyhow!("unrecognized image: {}", e))?;
+
+    let (width, height) = img.dimensions();
+    let side = width.min(height);
+    let x = (width - side) / 2;
+    let y = (height - side) / 2;
+
+    let thumbnail = img
+        .crop_imm(x, y, side, side)
+        .resize_exact(AVATAR_SIZE, AVATAR_SIZE, FilterType::Lanczos3);
+
+    let mut out = Vec::new();
+    thumbnail
+        .write_to(&mut std::io::Cursor::new(&mut out), image::ImageFormat::Png)
+        .map_err(|e| anyhow!("failed to encode thumbnail: {}", e))?;
+
+    Ok(out)
+}
@@ -0,0 +1,117 @@
+use anyhow::{anyhow, bail, Result};
+
+use super::{AppDb, Post, User};
+
+pub struct MemDb {
+    next_user_id: u64,
+    next_post_id: u64,
+    users: Vec<User>,
+    posts: Vec<Post>,
+}
+
+impl MemDb {
+    pub fn new() -> Self {
+        MemDb {
+            next_user_id: 0,
+            next_post_id: 0,
+            users: Vec::new(),
+            posts: Vec::new(),
+        }
+    }
+}
+
+impl AppDb for MemDb {
+    fn num_users(&self) -> u64 {
+        self.users.len() as u64
+    }
+
+    fn add_user(&mut self, mut user: User) -> Result<()> {
+        // no dupes :)
+        if self.users.iter().find(|x| x.name == user.name).is_some() {
+            bail!("user with name {} already exists", user.name);
+        }
+
+        user.id = self.next_user_id;
+        self.next_user_id += 1;
+
+        self.users.push(user);
+        Ok(())
+    }
+
+    fn update_user(&mut self, user: User) -> Result<()> {
+        // verify that user already exists
+        let user_ref = self
+            .users
+            .iter_mut()
+            .find(|x| x.name == user.name)
+            .ok_or(anyhow!("user with name {} not found", user.name))?;
+        *user_ref = user;
+        Ok(())
+    }
+
+    fn get_user_by_id(&self, id: u64) -> Result<User> {
+        self.users
+            .iter()
+            .find(|x| x.id == id)
+            .ok_or(anyhow!("user with id {} not found", id))
+            .cloned()
+    }
+
+    fn get_user_by_name(&self, name: &str) -> Result<User> {
+        self.users
+            .iter()
+            .find(|x| x.name == name)
+            .ok_or(anyhow!("user with name {} not found", name))
+            .cloned()
+    }
+
+    fn get_users(&self) -> Result<Vec<User>> {
+        Ok(self.users.clone())
+    }
+
+    fn delete_user(&mut self, id: u64) -> Result<()> {
+        let idx = self
+            .users
+            .iter()
+            .position(|x| x.id == id)
+            .ok_or(anyhow!("user with id {} not found", id))?;
+        self.users.swap_remove(idx);
+        Ok(())
+    }
+
+    fn num_posts(&self) -> u64 {
+        self.posts.len() as u64
+    }
+
+    fn add_post(&mut self, mut post: Post) -> Result<Post> {
+        post.id = self.next_post_id;
+        self.next_post_id += 1;
+
+        self.posts.push(post.clone());
+        Ok(post)
+    }
+
+    fn update_post(&mut self, post: Post) -> Result<()> {
+        let post_ref = self
+            .posts
+            .iter_mut()
+            .find(|x| x.id == post.id)
+            .ok_or(anyhow!("post with id {} not found", post.id))?;
+        *post_ref = post;
+        Ok(())
+    }
+
+    fn get_posts(&self) -> Result<Vec<Post>> {
+        Ok(self.posts.clone())
+    }
+
+    fn delete_post_by_id(&mut self, id: u64) -> Result<()> {
+        let idx = self
+            .posts
+            .iter()
+            .position(|x| x.id == id)
+            .ok_or(anyhow!("post with id {} not found", id))?;
+        self.posts.swap_remove(idx);
+        Ok(())
+    }
+}
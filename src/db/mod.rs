@@ -0,0 +1,289 @@
+use anyhow::{anyhow, Result};
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use chrono::Local;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+
+mod mem;
+mod sql;
+
+pub use mem::MemDb;
+pub use sql::SqlDb;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct User {
+    id: u64,
+    name: String,
+    #[serde(skip)]
+    password: String,
+    role: UserRole,
+
+    blue: bool,
+    disabled: bool,
+
+    bio: String,
+    avatar: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+pub enum UserRole {
+    User,
+    Admin,
+}
+
+impl User {
+    pub fn new(name: &str, password: &str, role: UserRole, blue: bool) -> Self {
+        Self {
+            id: 0,
+            name: name.to_owned(),
+            password: hash_password(password),
+            role,
+            blue,
+            disabled: false,
+            bio: String::new(),
+            avatar: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn role(&self) -> UserRole {
+        self.role
+    }
+
+    pub fn blue(&self) -> bool {
+        self.blue
+    }
+
+    pub fn disabled(&self) -> bool {
+        self.disabled
+    }
+
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
+    pub fn set_bio(&mut self, bio: &str) {
+        self.bio = bio.to_owned();
+    }
+
+    /// Path (relative to `/assets`) of the user's avatar thumbnail, if
+    /// they've uploaded one.
+    pub fn avatar(&self) -> Option<&str> {
+        self.avatar.as_deref()
+    }
+
+    pub fn set_avatar(&mut self, avatar: Option<String>) {
+        self.avatar = avatar;
+    }
+
+    pub fn set_role(&mut self, role: UserRole) {
+        self.role = role;
+    }
+
+    pub fn set_blue(&mut self, blue: bool) {
+        self.blue = blue;
+    }
+
+    /// Returns `true` if `password` matches the stored credential.
+    ///
+    /// Accounts created before the Argon2 migration still have their
+    /// plaintext password in this field; if a plaintext match succeeds we
+    /// transparently upgrade it to a PHC hash so it's never compared in the
+    /// clear again.
+    pub fn check_password(&mut self, password: &str) -> bool {
+        match PasswordHash::new(&self.password) {
+            Ok(hash) => Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok(),
+            Err(_) => {
+                // legacy plaintext row, pre Argon2 migration; compare in
+                // constant time since this isn't a digest comparison
+                if self.password.as_bytes().ct_eq(password.as_bytes()).into() {
+                    self.password = hash_password(password);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    pub fn change_password(&mut self, curr_pass: &str, new_pass: &str) -> Result<()> {
+        if self.check_password(curr_pass) {
+            self.password = hash_password(new_pass);
+            Ok(())
+        } else {
+            Err(anyhow!("incorrect password"))
+        }
+    }
+}
+
+/// Derives a PHC-formatted Argon2id hash from a freshly generated salt.
+fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("argon2 hashing with default params should never fail")
+        .to_string()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Post {
+    id: u64,
+    author_id: u64,
+    contents: String,
+
+    timestamp: u64,
+}
+
+impl Post {
+    pub fn new(author: &User, contents: &str) -> Self {
+        let now = Local::now().timestamp().try_into().unwrap();
+
+        Self {
+            id: 0,
+            author_id: author.id,
+            contents: contents.to_owned(),
+            timestamp: now,
+        }
+    }
+}
+
+/// Picks between the in-memory and SQL-backed [`AppDb`] implementors at
+/// startup (see `main`, which chooses a variant based on the
+/// `DATABASE_URL` environment variable) while keeping `AppState<D: AppDb>`
+/// generic over a single concrete type.
+pub enum Backend {
+    Mem(MemDb),
+    Sql(SqlDb),
+}
+
+impl AppDb for Backend {
+    fn num_users(&self) -> u64 {
+        match self {
+            Backend::Mem(db) => db.num_users(),
+            Backend::Sql(db) => db.num_users(),
+        }
+    }
+
+    fn add_user(&mut self, user: User) -> Result<()> {
+        match self {
+            Backend::Mem(db) => db.add_user(user),
+            Backend::Sql(db) => db.add_user(user),
+        }
+    }
+
+    fn update_user(&mut self, user: User) -> Result<()> {
+        match self {
+            Backend::Mem(db) => db.update_user(user),
+            Backend::Sql(db) => db.update_user(user),
+        }
+    }
+
+    fn get_user_by_id(&self, id: u64) -> Result<User> {
+        match self {
+            Backend::Mem(db) => db.get_user_by_id(id),
+            Backend::Sql(db) => db.get_user_by_id(id),
+        }
+    }
+
+    fn get_user_by_name(&self, name: &str) -> Result<User> {
+        match self {
+            Backend::Mem(db) => db.get_user_by_name(name),
+            Backend::Sql(db) => db.get_user_by_name(name),
+        }
+    }
+
+    fn get_users(&self) -> Result<Vec<User>> {
+        match self {
+            Backend::Mem(db) => db.get_users(),
+            Backend::Sql(db) => db.get_users(),
+        }
+    }
+
+    fn delete_user(&mut self, id: u64) -> Result<()> {
+        match self {
+            Backend::Mem(db) => db.delete_user(id),
+            Backend::Sql(db) => db.delete_user(id),
+        }
+    }
+
+    fn num_posts(&self) -> u64 {
+        match self {
+            Backend::Mem(db) => db.num_posts(),
+            Backend::Sql(db) => db.num_posts(),
+        }
+    }
+
+    fn add_post(&mut self, post: Post) -> Result<Post> {
+        match self {
+            Backend::Mem(db) => db.add_post(post),
+            Backend::Sql(db) => db.add_post(post),
+        }
+    }
+
+    fn update_post(&mut self, post: Post) -> Result<()> {
+        match self {
+            Backend::Mem(db) => db.update_post(post),
+            Backend::Sql(db) => db.update_post(post),
+        }
+    }
+
+    fn get_posts(&self) -> Result<Vec<Post>> {
+        match self {
+            Backend::Mem(db) => db.get_posts(),
+            Backend::Sql(db) => db.get_posts(),
+        }
+    }
+
+    fn delete_post_by_id(&mut self, id: u64) -> Result<()> {
+        match self {
+            Backend::Mem(db) => db.delete_post_by_id(id),
+            Backend::Sql(db) => db.delete_post_by_id(id),
+        }
+    }
+}
+
+/// Storage backend for users and posts.
+///
+/// Methods are synchronous because handlers hold the implementor behind a
+/// plain `Mutex<D>` (see `AppState`). [`SqlDb`] honors this by driving its
+/// async `sqlx` pool with a blocking `tokio` handle internally rather than
+/// widening this trait to `async fn` — see that module for the rationale.
+pub trait AppDb {
+    fn num_users(&self) -> u64;
+
+    fn add_user(&mut self, user: User) -> Result<()>;
+
+    fn update_user(&mut self, user: User) -> Result<()>;
+
+    fn get_user_by_id(&self, id: u64) -> Result<User>;
+
+    fn get_user_by_name(&self, name: &str) -> Result<User>;
+
+    fn get_users(&self) -> Result<Vec<User>>;
+
+    fn delete_user(&mut self, id: u64) -> Result<()>;
+
+    fn num_posts(&self) -> u64;
+
+    /// Inserts `post` and returns it with the id the backend assigned.
+    fn add_post(&mut self, post: Post) -> Result<Post>;
+
+    fn update_post(&mut self, post: Post) -> Result<()>;
+
+    fn get_posts(&self) -> Result<Vec<Post>>;
+
+    fn delete_post_by_id(&mut self, id: u64) -> Result<()>;
+}
@@ -0,0 +1,279 @@
+//! Persistent [`AppDb`] backed by `sqlx`, with SQLite as the driver.
+//!
+//! `AppDb` is a synchronous trait because every handler reaches it through a
+//! plain `std::sync::Mutex<D>` (see `AppState`). Rather than widen the trait
+//! to `async fn` -- which would mean touching every handler and swapping the
+//! `Mutex` for an async-aware lock -- `SqlDb` keeps the sync surface and
+//! drives its async pool with `tokio::task::block_in_place` +
+//! `Handle::block_on`. That only works on the multi-threaded runtime (the
+//! default for `#[tokio::main]`), which is what `main` already uses.
+
+use anyhow::{anyhow, Result};
+use tokio::runtime::Handle;
+
+type Pool = sqlx::SqlitePool;
+
+use super::{AppDb, Post, User, UserRole};
+
+pub struct SqlDb {
+    pool: Pool,
+    rt: Handle,
+}
+
+impl SqlDb {
+    /// Connects to `url` (e.g. `sqlite://tweeter.db`) and applies any
+    /// pending migrations from `migrations/` before returning.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let pool = Pool::connect(url).await?;
+        sqlx::migrate!("./migrations").run(&pool).await?;
+
+        Ok(Self {
+            pool,
+            rt: Handle::current(),
+        })
+    }
+
+    fn block_on<F: std::future::Future>(&self, fut: F) -> F::Output {
+        tokio::task::block_in_place(|| self.rt.block_on(fut))
+    }
+}
+
+fn role_to_str(role: UserRole) -> &'static str {
+    match role {
+        UserRole::User => "user",
+        UserRole::Admin => "admin",
+    }
+}
+
+fn role_from_str(role: &str) -> Result<UserRole> {
+    match role {
+        "user" => Ok(UserRole::User),
+        "admin" => Ok(UserRole::Admin),
+        other => Err(anyhow!("unknown role {}", other)),
+    }
+}
+
+impl AppDb for SqlDb {
+    fn num_users(&self) -> u64 {
+        self.block_on(async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users")
+                .fetch_one(&self.pool)
+                .await
+                .unwrap_or(0) as u64
+        })
+    }
+
+    fn add_user(&mut self, user: User) -> Result<()> {
+        self.block_on(async {
+            sqlx::query(
+                "INSERT INTO users (name, password, role, blue, disabled, bio, avatar) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&user.name)
+            .bind(&user.password)
+            .bind(role_to_str(user.role))
+            .bind(user.blue)
+            .bind(user.disabled)
+            .bind(&user.bio)
+            .bind(&user.avatar)
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| anyhow!("failed to add user {}: {}", user.name, e))
+        })
+    }
+
+    fn update_user(&mut self, user: User) -> Result<()> {
+        self.block_on(async {
+            sqlx::query(
+                "UPDATE users SET password = ?, role = ?, blue = ?, disabled = ?, bio = ?, avatar = ? WHERE id = ?",
+            )
+            .bind(&user.password)
+            .bind(role_to_str(user.role))
+            .bind(user.blue)
+            .bind(user.disabled)
+            .bind(&user.bio)
+            .bind(&user.avatar)
+            .bind(user.id as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("failed to update user {}: {}", user.id, e))
+            .and_then(|res| {
+                if res.rows_affected() == 0 {
+                    Err(anyhow!("user with id {} not found", user.id))
+                } else {
+                    Ok(())
+                }
+            })
+        })
+    }
+
+    fn get_user_by_id(&self, id: u64) -> Result<User> {
+        self.block_on(async {
+            let row = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE id = ?")
+                .bind(id as i64)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| anyhow!("user with id {} not found", id))?;
+            row.try_into()
+        })
+    }
+
+    fn get_user_by_name(&self, name: &str) -> Result<User> {
+        self.block_on(async {
+            let row = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE name = ?")
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| anyhow!("user with name {} not found", name))?;
+            row.try_into()
+        })
+    }
+
+    fn get_users(&self) -> Result<Vec<User>> {
+        self.block_on(async {
+            sqlx::query_as::<_, UserRow>("SELECT * FROM users")
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect()
+        })
+    }
+
+    fn delete_user(&mut self, id: u64) -> Result<()> {
+        self.block_on(async {
+            sqlx::query("DELETE FROM users WHERE id = ?")
+                .bind(id as i64)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| anyhow!("failed to delete user {}: {}", id, e))
+                .and_then(|res| {
+                    if res.rows_affected() == 0 {
+                        Err(anyhow!("user with id {} not found", id))
+                    } else {
+                        Ok(())
+                    }
+                })
+        })
+    }
+
+    fn num_posts(&self) -> u64 {
+        self.block_on(async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM posts")
+                .fetch_one(&self.pool)
+                .await
+                .unwrap_or(0) as u64
+        })
+    }
+
+    fn add_post(&mut self, mut post: Post) -> Result<Post> {
+        self.block_on(async {
+            let res = sqlx::query(
+                "INSERT INTO posts (author_id, contents, timestamp) VALUES (?, ?, ?)",
+            )
+            .bind(post.author_id as i64)
+            .bind(&post.contents)
+            .bind(post.timestamp as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("failed to add post: {}", e))?;
+
+            post.id = res.last_insert_rowid() as u64;
+            Ok(post)
+        })
+    }
+
+    fn update_post(&mut self, post: Post) -> Result<()> {
+        self.block_on(async {
+            sqlx::query("UPDATE posts SET contents = ? WHERE id = ?")
+                .bind(&post.contents)
+                .bind(post.id as i64)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| anyhow!("failed to update post {}: {}", post.id, e))
+                .and_then(|res| {
+                    if res.rows_affected() == 0 {
+                        Err(anyhow!("post with id {} not found", post.id))
+                    } else {
+                        Ok(())
+                    }
+                })
+        })
+    }
+
+    fn get_posts(&self) -> Result<Vec<Post>> {
+        self.block_on(async {
+            Ok(sqlx::query_as::<_, PostRow>("SELECT * FROM posts")
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect())
+        })
+    }
+
+    fn delete_post_by_id(&mut self, id: u64) -> Result<()> {
+        self.block_on(async {
+            sqlx::query("DELETE FROM posts WHERE id = ?")
+                .bind(id as i64)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| anyhow!("failed to delete post {}: {}", id, e))
+                .and_then(|res| {
+                    if res.rows_affected() == 0 {
+                        Err(anyhow!("post with id {} not found", id))
+                    } else {
+                        Ok(())
+                    }
+                })
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct UserRow {
+    id: i64,
+    name: String,
+    password: String,
+    role: String,
+    blue: bool,
+    disabled: bool,
+    bio: String,
+    avatar: Option<String>,
+}
+
+impl TryFrom<UserRow> for User {
+    type Error = anyhow::Error;
+
+    fn try_from(row: UserRow) -> Result<Self> {
+        Ok(User {
+            id: row.id as u64,
+            name: row.name,
+            password: row.password,
+            role: role_from_str(&row.role)?,
+            blue: row.blue,
+            disabled: row.disabled,
+            bio: row.bio,
+            avatar: row.avatar,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PostRow {
+    id: i64,
+    author_id: i64,
+    contents: String,
+    timestamp: i64,
+}
+
+impl From<PostRow> for Post {
+    fn from(row: PostRow) -> Self {
+        Post {
+            id: row.id as u64,
+            author_id: row.author_id as u64,
+            contents: row.contents,
+            timestamp: row.timestamp as u64,
+        }
+    }
+}
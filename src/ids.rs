@@ -0,0 +1,65 @@
+//! Encodes internal `u64` ids as short, non-sequential, URL-safe strings
+//! (via `sqids`) so outward-facing links -- profile and avatar URLs -- don't
+//! hand out a dense, enumerable keyspace the way the raw database ids do.
+//!
+//! `sqids`'s own alphabet shuffle is a fixed, public algorithm, so encoding
+//! with the crate default alphabet only obscures ids cosmetically -- anyone
+//! can pull in `sqids` and replay `encode(&[0])`, `encode(&[1])`, ... to walk
+//! every id. [`init`] permutes the alphabet with this deployment's signing
+//! key first, so the mapping can't be reproduced without that secret.
+
+use once_cell::sync::OnceCell;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use sqids::{Sqids, DEFAULT_ALPHABET};
+
+static SQIDS: OnceCell<Sqids> = OnceCell::new();
+
+/// Derives a secret-permuted alphabet from `secret` and installs it for
+/// [`encode`]/[`decode`]. Must be called exactly once at startup, before the
+/// server starts accepting requests.
+pub fn init(secret: &[u8]) {
+    let mut seed = [0u8; 32];
+    for (i, byte) in secret.iter().enumerate() {
+        seed[i % seed.len()] ^= *byte;
+    }
+
+    let mut alphabet: Vec<char> = DEFAULT_ALPHABET.chars().collect();
+    alphabet.shuffle(&mut StdRng::from_seed(seed));
+
+    let sqids = Sqids::builder()
+        .alphabet(alphabet)
+        .min_length(6)
+        .build()
+        .expect("a shuffled copy of the default alphabet is always valid");
+
+    SQIDS.set(sqids).ok();
+}
+
+fn sqids() -> &'static Sqids {
+    SQIDS
+        .get()
+        .expect("ids::init must run before encoding or decoding ids")
+}
+
+/// Encodes an internal id for use in a URL.
+pub fn encode(id: u64) -> String {
+    sqids().encode(&[id]).expect("encoding a single u64 never fails")
+}
+
+/// Decodes a value previously produced by [`encode`]. Returns `None` for
+/// anything that isn't a valid encoding of exactly one id -- callers should
+/// treat that as a 404, not a panic.
+pub fn decode(encoded: &str) -> Option<u64> {
+    let ids = sqids().decode(encoded);
+    match ids.as_slice() {
+        // sqids will parse some strings that `encode` never produces; only
+        // accept the canonical form so each id has exactly one valid string
+        [id] if encode(*id) == encoded => Some(*id),
+        _ => None,
+    }
+}
+
+/// Minijinja filter wrapper so templates can render `{{ user.id|encode_id }}`.
+pub fn encode_id_filter(id: u64) -> String {
+    encode(id)
+}
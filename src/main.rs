@@ -6,7 +6,7 @@ use std::{
 
 use anyhow::anyhow;
 use axum::{
-    extract::{FromRef, State, Path},
+    extract::{FromRef, Multipart, State, Path},
     http::{Request, StatusCode},
     middleware::{self, Next},
     response::{Html, IntoResponse, Redirect, Response, Result},
@@ -20,21 +20,71 @@ use axum_extra::extract::{
 };
 use chrono::{TimeZone, Local};
 use minijinja::{context, path_loader, Environment};
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::Deserialize;
 
 use tower_http::{trace::TraceLayer, services::ServeDir};
 
-use db::{AppDb, MemDb, User, UserRole};
+use db::{AppDb, Backend, MemDb, SqlDb, User, UserRole};
 use tracing::Level;
 use tracing_subscriber::{filter, prelude::*};
 use urlencoding::encode;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 use crate::db::Post;
 
+mod api;
+mod avatar;
 mod db;
+mod ids;
 
 const VIEWS_DIR: &str = "views/";
 
+static USERNAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z0-9_]{3,32}$").unwrap());
+
+/// Joins every message from a failed [`Validate::validate`] call into one
+/// string, for use with the existing `#{err}` / `?err=` redirect pattern.
+fn validation_err_string(errors: ValidationErrors) -> String {
+    errors
+        .field_errors()
+        .into_values()
+        .flatten()
+        .map(|e| {
+            e.message
+                .clone()
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| e.code.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Loads the session user by name and rejects disabled accounts, so every
+/// authenticated route enforces moderation the same way instead of each
+/// handler re-deriving it (and some forgetting to).
+fn require_active_user(db: &impl AppDb, username: &str) -> std::result::Result<User, Redirect> {
+    let user = db
+        .get_user_by_name(username)
+        .map_err(|_| Redirect::to("/logout"))?;
+
+    if user.disabled() {
+        Err(Redirect::to("/logout"))
+    } else {
+        Ok(user)
+    }
+}
+
+fn validate_optional_password(password: &str) -> Result<(), ValidationError> {
+    if password.is_empty() || password.chars().count() >= 8 {
+        Ok(())
+    } else {
+        let mut err = ValidationError::new("password_too_short");
+        err.message = Some("password must be at least 8 characters".into());
+        Err(err)
+    }
+}
+
 pub struct AppState<D: AppDb>(Arc<InnerState<D>>);
 
 impl<D: AppDb> Clone for AppState<D> {
@@ -44,10 +94,10 @@ impl<D: AppDb> Clone for AppState<D> {
 }
 
 impl<D: AppDb> AppState<D> {
-    pub fn new(db: D, env: Environment<'static>) -> Self {
+    pub fn new(db: D, env: Environment<'static>, key: Key) -> Self {
         Self(Arc::new(InnerState {
             db: Mutex::new(db),
-            key: Key::generate(),
+            key,
             env,
         }))
     }
@@ -57,7 +107,7 @@ impl<D: AppDb> Deref for AppState<D> {
     type Target = InnerState<D>;
 
     fn deref(&self) -> &Self::Target {
-        &*self.0
+        &self.0
     }
 }
 
@@ -86,15 +136,33 @@ async fn main() {
         .with(filter)
         .init();
 
+    // Generated once per process and reused for both the cookie/JWT signing
+    // key and (via `ids::init`) the sqid alphabet permutation, so opaque ids
+    // can't be reproduced without this deployment's secret.
+    let key = Key::generate();
+    ids::init(key.master());
+
     let mut env = Environment::new();
     env.set_loader(path_loader(VIEWS_DIR));
     env.add_filter("format_time", |timestamp: u64| {
         let datetime = Local.timestamp_opt(timestamp as i64, 0).unwrap();
         datetime.format("%b %-d, %Y %-I:%M:%S").to_string()
     });
+    env.add_filter("encode_id", ids::encode_id_filter);
+
+    // `DATABASE_URL` picks the persistent SQLite backend; unset falls back
+    // to the in-memory store used by default in development.
+    let backend = match std::env::var("DATABASE_URL") {
+        Ok(url) => Backend::Sql(
+            SqlDb::connect(&url)
+                .await
+                .expect("failed to connect to DATABASE_URL"),
+        ),
+        Err(_) => Backend::Mem(MemDb::new()),
+    };
 
-    let state = AppState::new(MemDb::new(), env);
-    {
+    let state = AppState::new(backend, env, key);
+    if state.db.lock().unwrap().num_users() == 0 {
         let mut db = state.db.lock().unwrap();
         let admin = User::new("admin", "pepegaman123", UserRole::Admin, true);
         db.add_user(admin).unwrap();
@@ -103,11 +171,13 @@ async fn main() {
     let admin_router = Router::new()
         .route("/admin", get(get_admin))
         .route("/admin/users", get(get_users_admin).post(post_users_admin))
+        .route("/admin/posts", get(get_posts_admin).post(post_posts_admin))
         .with_state(state.clone())
         .layer(middleware::from_fn_with_state(state.clone(), auth_admin));
 
     let app = Router::new()
         .nest_service("/assets", ServeDir::new("assets"))
+        .nest("/api/v1", api::router())
         .route("/", get(get_index))
         .route("/register", get(get_register).post(post_register))
         .route("/login", get(get_login).post(post_login))
@@ -115,6 +185,7 @@ async fn main() {
         .route("/create_post", get(get_create_post).post(post_create_post))
         .route("/profile/:user_id", get(get_profile))
         .route("/settings", get(get_settings).post(post_settings))
+        .route("/settings/avatar", post(post_settings_avatar))
         .merge(admin_router)
         .with_state(state)
         .layer(TraceLayer::new_for_http());
@@ -170,10 +241,17 @@ async fn get_register(State(state): State<AppState<impl AppDb>>, jar: SignedCook
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct SignUp {
+    #[validate(regex(
+        path = "USERNAME_RE",
+        message = "username must be 3-32 alphanumeric/underscore characters"
+    ))]
     username: String,
+    #[validate(length(min = 8, message = "password must be at least 8 characters"))]
     password: String,
+    #[validate(must_match(other = "password", message = "passwords do not match"))]
+    password_confirm: String,
 }
 
 async fn post_register(
@@ -182,6 +260,13 @@ async fn post_register(
     Form(sign_up): Form<SignUp>,
 ) -> Result<(SignedCookieJar, Redirect)> {
     if jar.get("user").is_none() {
+        sign_up.validate().map_err(|errs| {
+            Redirect::to(&format!(
+                "/register#{}",
+                encode(&validation_err_string(errs))
+            ))
+        })?;
+
         let new_user = User::new(&sign_up.username, &sign_up.password, UserRole::User, false);
 
         state
@@ -209,9 +294,11 @@ async fn get_login(State(state): State<AppState<impl AppDb>>, jar: SignedCookieJ
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct SignIn {
+    #[validate(length(min = 1, message = "username is required"))]
     username: String,
+    #[validate(length(min = 1, message = "password is required"))]
     password: String,
 }
 
@@ -221,17 +308,27 @@ async fn post_login(
     Form(sign_in): Form<SignIn>,
 ) -> Result<(SignedCookieJar, Redirect)> {
     if jar.get("user").is_none() {
-        let db = state
+        sign_in.validate().map_err(|errs| {
+            Redirect::to(&format!(
+                "/login?err={}",
+                encode(&validation_err_string(errs))
+            ))
+        })?;
+
+        let mut db = state
             .db
             .lock()
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-        db.get_user_by_name(&sign_in.username)
-            .and_then(|user| {
-                if user.check_password(&sign_in.password) {
-                    Ok(user)
-                } else {
+        let user = db
+            .get_user_by_name(&sign_in.username)
+            .and_then(|mut user| {
+                if !user.check_password(&sign_in.password) {
                     Err(anyhow!("incorrect password"))
+                } else if user.disabled() {
+                    Err(anyhow!("account disabled"))
+                } else {
+                    Ok(user)
                 }
             })
             .map_err(|_| {
@@ -241,6 +338,9 @@ async fn post_login(
                 ))
             })?;
 
+        // persists the legacy-plaintext-to-Argon2 upgrade, if one happened
+        db.update_user(user).ok();
+
         jar = jar.add(Cookie::new("user", sign_in.username));
     }
 
@@ -285,7 +385,7 @@ async fn post_create_post(
     };
 
     let mut db = state.db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let user = db.get_user_by_name(username.value()).map_err(|_| Redirect::to("/logout"))?;
+    let user = require_active_user(&*db, username.value())?;
 
     let post = Post::new(&user, &form.contents);
     db.add_post(post).unwrap();
@@ -295,8 +395,11 @@ async fn post_create_post(
 
 async fn get_profile(
     State(state): State<AppState<impl AppDb>>,
-    Path(user_id): Path<u64>,
+    Path(encoded_id): Path<String>,
 ) -> Result<Html<String>> {
+    let user_id = ids::decode(&encoded_id)
+        .ok_or((StatusCode::NOT_FOUND, "no such profile".to_owned()))?;
+
     let db = state.db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let user = db.get_user_by_id(user_id).map_err(|_| (StatusCode::NOT_FOUND, format!("no user with id {}", user_id)))?;
 
@@ -315,9 +418,7 @@ async fn get_settings(
             .db
             .lock()
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        let user = db
-            .get_user_by_name(username.value())
-            .map_err(|_| Redirect::to("/logout"))?;
+        let user = require_active_user(&*db, username.value())?;
 
         let tmpl = state.env.get_template("settings.html").unwrap();
         Ok(Html(
@@ -332,10 +433,12 @@ async fn get_settings(
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct Settings {
     currpass: String,
+    #[validate(custom = "validate_optional_password")]
     newpass: String,
+    #[validate(length(max = 280, message = "bio must be 280 characters or fewer"))]
     bio: String,
 }
 
@@ -345,13 +448,18 @@ async fn post_settings(
     Form(settings): Form<Settings>,
 ) -> Result<Response> {
     if let Some(username) = jar.get("user") {
+        settings.validate().map_err(|errs| {
+            Redirect::to(&format!(
+                "/settings?err={}",
+                encode(&validation_err_string(errs))
+            ))
+        })?;
+
         let mut db = state
             .db
             .lock()
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        let mut user = db
-            .get_user_by_name(username.value())
-            .map_err(|_| Redirect::to("/logout"))?;
+        let mut user = require_active_user(&*db, username.value())?;
 
         if !settings.newpass.is_empty() {
             user.change_password(&settings.currpass, &settings.newpass)
@@ -376,6 +484,64 @@ async fn post_settings(
     }
 }
 
+const AVATAR_DIR: &str = "assets/avatars";
+
+async fn post_settings_avatar(
+    State(state): State<AppState<impl AppDb>>,
+    jar: SignedCookieJar,
+    mut multipart: Multipart,
+) -> Result<Redirect> {
+    let Some(username) = jar.get("user") else {
+        return Ok(Redirect::to("/login"));
+    };
+
+    // Fetch the user and release the app-wide lock before reading the
+    // (client-controlled, potentially slow) multipart body -- holding a
+    // `MutexGuard` across an `.await` would also make this handler's future
+    // non-`Send`, which axum's `Handler` impl requires.
+    let mut user = {
+        let db = state.db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        require_active_user(&*db, username.value())?
+    };
+
+    let field = multipart
+        .next_field()
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let content_type = field.content_type().unwrap_or_default().to_owned();
+    if !avatar::is_allowed_mime_type(&content_type) {
+        return Ok(Redirect::to(&format!(
+            "/settings?err={}",
+            encode("unsupported image type")
+        )));
+    }
+
+    let bytes = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+    let thumbnail = avatar::process_to_thumbnail(&bytes).map_err(|e| {
+        Redirect::to(&format!("/settings?err={}", encode(&e.to_string())))
+    })?;
+
+    std::fs::create_dir_all(AVATAR_DIR).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let filename = format!("{}.png", ids::encode(user.id()));
+    std::fs::write(format!("{}/{}", AVATAR_DIR, filename), thumbnail)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    user.set_avatar(Some(format!("avatars/{}", filename)));
+    state
+        .db
+        .lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .update_user(user)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Redirect::to(&format!(
+        "/settings?success={}",
+        encode("Successfully updated avatar")
+    )))
+}
+
 async fn auth_admin<B>(
     State(state): State<AppState<impl AppDb>>,
     jar: SignedCookieJar,
@@ -392,7 +558,7 @@ async fn auth_admin<B>(
                 .map_err(|_| StatusCode::UNAUTHORIZED)?
         };
 
-        let resp = if user.role() == UserRole::Admin {
+        let resp = if user.role() == UserRole::Admin && !user.disabled() {
             Ok(next.run(request).await)
         } else {
             Err(StatusCode::UNAUTHORIZED)
@@ -404,9 +570,18 @@ async fn auth_admin<B>(
     Err(StatusCode::UNAUTHORIZED)
 }
 
-async fn get_admin(State(state): State<AppState<impl AppDb>>) -> Html<String> {
+async fn get_admin(State(state): State<AppState<impl AppDb>>) -> Result<Html<String>> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let num_users = db.num_users();
+    let num_posts = db.num_posts();
+
     let tmpl = state.env.get_template("admin/index.html").unwrap();
-    Html(tmpl.render(context! {}).unwrap())
+    Ok(Html(
+        tmpl.render(context! { num_users, num_posts }).unwrap(),
+    ))
 }
 
 async fn get_users_admin(State(state): State<AppState<impl AppDb>>) -> Result<Html<String>> {
@@ -425,6 +600,11 @@ async fn get_users_admin(State(state): State<AppState<impl AppDb>>) -> Result<Ht
 enum UserCmd {
     GrantBlue,
     RemoveBlue,
+    Disable,
+    Enable,
+    Promote,
+    Demote,
+    Delete,
 }
 
 #[derive(Deserialize)]
@@ -442,13 +622,61 @@ async fn post_users_admin(
         .lock()
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    if let UserCmd::Delete = form.cmd {
+        db.delete_user(form.id).map_err(|_| StatusCode::BAD_REQUEST)?;
+        return Ok(Redirect::to("/admin/users"));
+    }
+
     let mut user = db.get_user_by_id(form.id).map_err(|_| StatusCode::BAD_REQUEST)?;
     match form.cmd {
         UserCmd::GrantBlue => user.set_blue(true),
         UserCmd::RemoveBlue => user.set_blue(false),
+        UserCmd::Disable => user.set_disabled(true),
+        UserCmd::Enable => user.set_disabled(false),
+        UserCmd::Promote => user.set_role(UserRole::Admin),
+        UserCmd::Demote => user.set_role(UserRole::User),
+        UserCmd::Delete => unreachable!("handled above"),
     };
 
     db.update_user(user).unwrap();
 
     Ok(Redirect::to("/admin/users"))
+}
+
+async fn get_posts_admin(State(state): State<AppState<impl AppDb>>) -> Result<Html<String>> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let posts = db
+        .get_posts()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let user_map: HashMap<u64, User> = db
+        .get_users()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|user| (user.id(), user))
+        .collect();
+
+    let tmpl = state.env.get_template("admin/posts.html").unwrap();
+    Ok(Html(tmpl.render(context! { posts, user_map }).unwrap()))
+}
+
+#[derive(Deserialize)]
+struct DeletePost {
+    id: u64,
+}
+
+async fn post_posts_admin(
+    State(state): State<AppState<impl AppDb>>,
+    Form(form): Form<DeletePost>,
+) -> Result<Redirect> {
+    let mut db = state
+        .db
+        .lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    db.delete_post_by_id(form.id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    Ok(Redirect::to("/admin/posts"))
 }
\ No newline at end of file